@@ -34,4 +34,4 @@ mod test {
         assert!(include_path().exists());
         assert!(protoc_bin_path().exists());
     }
-}
\ No newline at end of file
+}
@@ -12,6 +12,16 @@
 //!
 //! returns a path to a `protoc` binary packaged into the crate.
 //!
+//! `protoc_bin_path()` can be overridden with the `PROTOC_BIN_VENDORED_PROTOC`
+//! environment variable (or `PROTOC` as a fallback): if set and pointing at
+//! an existing file, that path is returned as is, and the vendored binary
+//! for the current platform is never consulted. This is useful on platforms
+//! this crate does not vendor a binary for, or when a pinned system `protoc`
+//! must be used instead. Callers that invoke this from a build script should
+//! emit `cargo:rerun-if-env-changed=PROTOC_BIN_VENDORED_PROTOC` and
+//! `cargo:rerun-if-env-changed=PROTOC` themselves, since this crate has no
+//! build script of its own to do it for them.
+//!
 //! Crate also packs `.proto` files distributed with protobuf:
 //!
 //! ```no_run
@@ -25,22 +35,36 @@
 
 use std::env;
 use std::fmt;
+use std::fs;
+use std::path::Path;
 use std::path::PathBuf;
 
 /// Error returned when a binary is not available.
 #[derive(Debug)]
 pub struct Error {
-    os: &'static str,
-    arch: &'static str,
+    kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ErrorKind {
+    UnsupportedPlatform { os: &'static str, arch: &'static str },
+    DocsRs,
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "protoc binary cannot be found for platform {}-{}",
-            self.os, self.arch
-        )
+        match self.kind {
+            ErrorKind::UnsupportedPlatform { os, arch } => write!(
+                f,
+                "protoc binary cannot be found for platform {}-{}",
+                os, arch
+            ),
+            ErrorKind::DocsRs => write!(
+                f,
+                "protoc binary is not available under docs.rs (DOCS_RS is set); \
+                 use try_protoc_bin_path()/try_include_path() instead"
+            ),
+        }
     }
 }
 
@@ -53,6 +77,9 @@ enum ArchCrate {
     Linux_Aarch_64,
     Linux_Ppcle_64,
     Linux_S390_64,
+    Linux_Riscv_64,
+    Linux_Loongarch_64,
+    Linux_Arm_32,
     Macos_Aarch_64,
     Macos_x86_64,
     Win32,
@@ -66,31 +93,128 @@ impl ArchCrate {
             ("linux", "aarch64") => ArchCrate::Linux_Aarch_64,
             ("linux", "powerpc64") => ArchCrate::Linux_Ppcle_64,
             ("linux", "s390x") => ArchCrate::Linux_S390_64,
+            ("linux", "riscv64") => ArchCrate::Linux_Riscv_64,
+            ("linux", "loongarch64") => ArchCrate::Linux_Loongarch_64,
+            ("linux", "arm") => ArchCrate::Linux_Arm_32,
             ("macos", "x86_64") => ArchCrate::Macos_x86_64,
             ("macos", "aarch64") => ArchCrate::Macos_Aarch_64,
             ("windows", _) => ArchCrate::Win32,
-            (os, arch) => return Err(Error { os, arch }),
+            (os, arch) => {
+                return Err(Error {
+                    kind: ErrorKind::UnsupportedPlatform { os, arch },
+                })
+            }
         })
     }
 }
 
+/// Look up `PROTOC_BIN_VENDORED_PROTOC` and `PROTOC` and return the path
+/// they point to, if set and pointing at an existing file.
+fn protoc_bin_path_override() -> Option<PathBuf> {
+    for var in ["PROTOC_BIN_VENDORED_PROTOC", "PROTOC"] {
+        if let Some(path) = env::var_os(var) {
+            let path = PathBuf::from(path);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+/// Whether the `DOCS_RS` environment variable is set, i.e. the crate is being
+/// built by docs.rs. Other introspecting tools (such as rust-analyzer) are
+/// not detected.
+fn is_docs_rs() -> bool {
+    env::var_os("DOCS_RS").is_some()
+}
+
 /// Return a path to `protoc` binary.
 ///
-/// This function returns an error when binary is not available for
-/// the current operating system and architecture.
+/// If `PROTOC_BIN_VENDORED_PROTOC` (or, failing that, `PROTOC`) is set and
+/// points at an existing file, that path is returned directly, bypassing
+/// platform detection entirely.
+///
+/// Returns an error under `DOCS_RS` (see [`try_protoc_bin_path`]), or when
+/// binary is not available for the current operating system and
+/// architecture.
 pub fn protoc_bin_path() -> Result<PathBuf, Error> {
+    if let Some(path) = protoc_bin_path_override() {
+        return Ok(path);
+    }
+    if is_docs_rs() {
+        return Err(Error {
+            kind: ErrorKind::DocsRs,
+        });
+    }
     Ok(match ArchCrate::detect()? {
         ArchCrate::Linux_X86_32 => protoc_bin_vendored_linux_x86_32::protoc_bin_path(),
         ArchCrate::Linux_X86_64 => protoc_bin_vendored_linux_x86_64::protoc_bin_path(),
         ArchCrate::Linux_Aarch_64 => protoc_bin_vendored_linux_aarch_64::protoc_bin_path(),
         ArchCrate::Linux_Ppcle_64 => protoc_bin_vendored_linux_ppcle_64::protoc_bin_path(),
         ArchCrate::Linux_S390_64 => protoc_bin_vendored_linux_s390_64::protoc_bin_path(),
+        ArchCrate::Linux_Riscv_64 => protoc_bin_vendored_linux_riscv64::protoc_bin_path(),
+        ArchCrate::Linux_Loongarch_64 => protoc_bin_vendored_linux_loongarch64::protoc_bin_path(),
+        ArchCrate::Linux_Arm_32 => protoc_bin_vendored_linux_arm32::protoc_bin_path(),
         ArchCrate::Macos_Aarch_64 => protoc_bin_vendored_macos_aarch_64::protoc_bin_path(),
         ArchCrate::Macos_x86_64 => protoc_bin_vendored_macos_x86_64::protoc_bin_path(),
         ArchCrate::Win32 => protoc_bin_vendored_win32::protoc_bin_path(),
     })
 }
 
+/// Like [`protoc_bin_path`], but returns `None` wherever it would return an
+/// error: when running under `docs.rs`, where the vendored binary may be
+/// unusable or absent, and when no binary is available for the current
+/// platform.
+pub fn try_protoc_bin_path() -> Option<PathBuf> {
+    protoc_bin_path().ok()
+}
+
+/// Name of the `protoc` executable on the current platform.
+fn protoc_exe_name() -> &'static str {
+    if cfg!(windows) {
+        "protoc.exe"
+    } else {
+        "protoc"
+    }
+}
+
+#[cfg(unix)]
+fn is_executable_file(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    match fs::metadata(path) {
+        Ok(metadata) => metadata.is_file() && metadata.permissions().mode() & 0o111 != 0,
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_executable_file(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Search `PATH` for an executable named `protoc` (`protoc.exe` on Windows).
+fn find_protoc_in_path() -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path)
+        .map(|dir| dir.join(protoc_exe_name()))
+        .find(|candidate| is_executable_file(candidate))
+}
+
+/// Return a path to `protoc` binary, like [`protoc_bin_path`], but fall back
+/// to searching `PATH` for a system `protoc` when the current platform has
+/// no vendored binary (e.g. BSDs, illumos, or architectures not yet vendored
+/// by this crate).
+///
+/// Returns an error only when neither a vendored binary nor a `protoc` on
+/// `PATH` could be found.
+pub fn protoc_bin_path_or_system() -> Result<PathBuf, Error> {
+    match protoc_bin_path() {
+        Ok(path) => Ok(path),
+        Err(err) => find_protoc_in_path().ok_or(err),
+    }
+}
+
 pub(crate) fn include_path_for_arch(arch_crate: &ArchCrate) -> PathBuf {
     match arch_crate {
         ArchCrate::Linux_X86_32 => protoc_bin_vendored_linux_x86_32::include_path(),
@@ -98,6 +222,9 @@ pub(crate) fn include_path_for_arch(arch_crate: &ArchCrate) -> PathBuf {
         ArchCrate::Linux_Aarch_64 => protoc_bin_vendored_linux_aarch_64::include_path(),
         ArchCrate::Linux_Ppcle_64 => protoc_bin_vendored_linux_ppcle_64::include_path(),
         ArchCrate::Linux_S390_64 => protoc_bin_vendored_linux_s390_64::include_path(),
+        ArchCrate::Linux_Riscv_64 => protoc_bin_vendored_linux_riscv64::include_path(),
+        ArchCrate::Linux_Loongarch_64 => protoc_bin_vendored_linux_loongarch64::include_path(),
+        ArchCrate::Linux_Arm_32 => protoc_bin_vendored_linux_arm32::include_path(),
         ArchCrate::Macos_Aarch_64 => protoc_bin_vendored_macos_aarch_64::include_path(),
         ArchCrate::Macos_x86_64 => protoc_bin_vendored_macos_x86_64::include_path(),
         ArchCrate::Win32 => protoc_bin_vendored_win32::include_path(),
@@ -107,23 +234,74 @@ pub(crate) fn include_path_for_arch(arch_crate: &ArchCrate) -> PathBuf {
 /// Include path which contains protobuf bundled `.proto` (like `descriptor.proto`).
 ///
 /// Include directory content is guaranteed to be identical regardless of the platform.
+///
+/// Returns an error under `DOCS_RS` (see [`try_include_path`]), or when
+/// binary is not available for the current operating system and
+/// architecture.
 pub fn include_path() -> Result<PathBuf, Error> {
+    if is_docs_rs() {
+        return Err(Error {
+            kind: ErrorKind::DocsRs,
+        });
+    }
     Ok(include_path_for_arch(&ArchCrate::detect()?))
 }
 
+/// Like [`include_path`], but returns `None` wherever it would return an
+/// error (under `docs.rs`, or on an unsupported platform). See
+/// [`try_protoc_bin_path`] for why this exists.
+pub fn try_include_path() -> Option<PathBuf> {
+    include_path().ok()
+}
+
+/// Version of the vendored `protoc` release, e.g. `"31.1"`.
+///
+/// This is a compile-time constant, read from `protoc-version.txt`, so
+/// reading it does not require locating or spawning the `protoc` binary.
+pub const PROTOC_VERSION: &str = include_str!("../protoc-version.txt");
+
+/// Same as [`PROTOC_VERSION`], provided as a function for convenience.
+pub fn protoc_version() -> &'static str {
+    PROTOC_VERSION
+}
+
+/// [`PROTOC_VERSION`] parsed into `(major, minor, patch)`.
+pub fn protoc_version_tuple() -> (u32, u32, u32) {
+    let mut parts = PROTOC_VERSION.trim().split('.');
+    let major = parts.next().unwrap().parse().unwrap();
+    let minor = parts.next().unwrap_or("0").parse().unwrap();
+    let patch = parts.next().unwrap_or("0").parse().unwrap();
+    (major, minor, patch)
+}
+
 #[cfg(test)]
 mod test {
+    use std::env;
     use std::fs;
     use std::io::Read;
     use std::path::Path;
     use std::path::PathBuf;
     use std::process;
+    use std::sync::Mutex;
+    use std::sync::MutexGuard;
 
     use crate::include_path_for_arch;
     use crate::ArchCrate;
 
+    /// `protoc_bin_path()` and friends read `PROTOC_BIN_VENDORED_PROTOC`,
+    /// `PROTOC` and `DOCS_RS`, and some tests mutate those process-wide
+    /// variables with `env::set_var`/`env::remove_var`. Tests run
+    /// concurrently by default, so any test that reads or writes these
+    /// variables must hold this lock for the duration.
+    static ENV_MUTEX: Mutex<()> = Mutex::new(());
+
+    fn lock_env() -> MutexGuard<'static, ()> {
+        ENV_MUTEX.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     #[test]
     fn include_path() {
+        let _guard = lock_env();
         assert!(crate::include_path()
             .unwrap()
             .join("google/protobuf/descriptor.proto")
@@ -185,6 +363,18 @@ mod test {
             &include_path_for_arch(&ArchCrate::Linux_X86_64),
             &include_path_for_arch(&ArchCrate::Linux_S390_64),
         );
+        compare_recursively(
+            &include_path_for_arch(&ArchCrate::Linux_X86_64),
+            &include_path_for_arch(&ArchCrate::Linux_Riscv_64),
+        );
+        compare_recursively(
+            &include_path_for_arch(&ArchCrate::Linux_X86_64),
+            &include_path_for_arch(&ArchCrate::Linux_Loongarch_64),
+        );
+        compare_recursively(
+            &include_path_for_arch(&ArchCrate::Linux_X86_64),
+            &include_path_for_arch(&ArchCrate::Linux_Arm_32),
+        );
         compare_recursively(
             &include_path_for_arch(&ArchCrate::Linux_X86_64),
             &include_path_for_arch(&ArchCrate::Macos_Aarch_64),
@@ -201,6 +391,7 @@ mod test {
 
     #[test]
     fn smoke() {
+        let _guard = lock_env();
         let process = process::Command::new(crate::protoc_bin_path().unwrap())
             .arg("--version")
             .stdin(process::Stdio::null())
@@ -211,4 +402,86 @@ mod test {
         process.stdout.unwrap().read_to_string(&mut stdout).unwrap();
         assert!(stdout.contains("libprotoc"), "stdout is: {:?}", stdout)
     }
+
+    #[test]
+    fn protoc_version_matches_binary() {
+        let _guard = lock_env();
+        let process = process::Command::new(crate::protoc_bin_path().unwrap())
+            .arg("--version")
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdout = String::new();
+        process.stdout.unwrap().read_to_string(&mut stdout).unwrap();
+        assert!(
+            stdout.contains(crate::protoc_version()),
+            "stdout is: {:?}, expected version {}",
+            stdout,
+            crate::protoc_version()
+        );
+
+        let (major, minor, _patch) = crate::protoc_version_tuple();
+        assert!(stdout.contains(&format!("{}.{}", major, minor)), "stdout is: {:?}", stdout);
+    }
+
+    #[test]
+    fn protoc_bin_path_or_system_prefers_vendored() {
+        let _guard = lock_env();
+        assert_eq!(
+            crate::protoc_bin_path().unwrap(),
+            crate::protoc_bin_path_or_system().unwrap()
+        );
+    }
+
+    #[test]
+    fn find_protoc_in_path_uses_path_fallback() {
+        let _guard = lock_env();
+
+        let dir = env::temp_dir().join(format!(
+            "protoc-bin-vendored-test-find-protoc-in-path-{}",
+            process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let fake_protoc = dir.join(if cfg!(windows) { "protoc.exe" } else { "protoc" });
+        fs::write(&fake_protoc, b"#!/bin/sh\nexit 0\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&fake_protoc, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        let old_path = env::var_os("PATH");
+        env::set_var("PATH", &dir);
+        let found = crate::find_protoc_in_path();
+        match old_path {
+            Some(old_path) => env::set_var("PATH", old_path),
+            None => env::remove_var("PATH"),
+        }
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(Some(fake_protoc), found);
+    }
+
+    #[test]
+    fn protoc_bin_path_override() {
+        let _guard = lock_env();
+        let overridden = crate::protoc_bin_path().unwrap();
+        env::set_var("PROTOC_BIN_VENDORED_PROTOC", &overridden);
+        assert_eq!(overridden, crate::protoc_bin_path().unwrap());
+        env::remove_var("PROTOC_BIN_VENDORED_PROTOC");
+    }
+
+    #[test]
+    fn try_protoc_bin_path_under_docs_rs() {
+        let _guard = lock_env();
+        assert_eq!(
+            Some(crate::protoc_bin_path().unwrap()),
+            crate::try_protoc_bin_path()
+        );
+        env::set_var("DOCS_RS", "1");
+        assert_eq!(None, crate::try_protoc_bin_path());
+        assert_eq!(None, crate::try_include_path());
+        env::remove_var("DOCS_RS");
+    }
 }